@@ -1,3 +1,6 @@
+use crate::crawl::CrawlConfig;
+use crate::embedding_provider::EmbeddingBackend;
+
 const ENV_SERVER_ADDRESS: &str = "MCP_SERVER_ADDRESS";
 const ENV_SERVER_PORT: &str = "MCP_SERVER_PORT";
 const ENV_ANTHROPIC_API_KEY: &str = "ANTHROPIC_API_KEY";
@@ -7,6 +10,32 @@ const ENV_UNISWAP_DOCS_DIR_V2: &str = "UNISWAP_DOCS_DIR_V2";
 const ENV_UNISWAP_DOCS_DIR_V3: &str = "UNISWAP_DOCS_DIR_V3";
 const ENV_UNISWAP_SOURCE_DIR_V2: &str = "UNISWAP_SOURCE_DIR_V2";
 const ENV_UNISWAP_SOURCE_DIR_V3: &str = "UNISWAP_SOURCE_DIR_V3";
+const ENV_EMBEDDING_BACKEND: &str = "EMBEDDING_BACKEND";
+const ENV_OLLAMA_BASE_URL: &str = "OLLAMA_BASE_URL";
+const ENV_OLLAMA_EMBEDDING_MODEL: &str = "OLLAMA_EMBEDDING_MODEL";
+const ENV_OLLAMA_EMBEDDING_DIMENSIONS: &str = "OLLAMA_EMBEDDING_DIMENSIONS";
+const ENV_CACHE_DIR: &str = "RIG_CACHE_DIR";
+const ENV_EMBEDDING_BATCH_SIZE: &str = "EMBEDDING_BATCH_SIZE";
+const ENV_RAG_K: &str = "RAG_K";
+const ENV_RAG_SCORE_THRESHOLD: &str = "RAG_SCORE_THRESHOLD";
+const ENV_RAG_MMR_LAMBDA: &str = "RAG_MMR_LAMBDA";
+
+const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+const DEFAULT_OLLAMA_EMBEDDING_MODEL: &str = "nomic-embed-text";
+const DEFAULT_OLLAMA_EMBEDDING_DIMENSIONS: usize = 768;
+const DEFAULT_CACHE_DIR: &str = ".rig-cache";
+const DEFAULT_EMBEDDING_BATCH_SIZE: usize = 100;
+const DEFAULT_RAG_K: usize = 10;
+const DEFAULT_RAG_SCORE_THRESHOLD: f64 = 0.75;
+const DEFAULT_RAG_MMR_LAMBDA: f64 = 0.7;
+
+const FLAG_REBUILD_INDEX: &str = "--rebuild-index";
+
+/// Checks process args for `--rebuild-index`, which clears the embedding
+/// cache before the index is rebuilt.
+pub fn rebuild_index_requested() -> bool {
+    std::env::args().any(|arg| arg == FLAG_REBUILD_INDEX)
+}
 
 pub fn get_env_var(name: &str) -> anyhow::Result<String> {
     let var = std::env::var(name)?;
@@ -23,27 +52,100 @@ pub fn get_bind_address() -> anyhow::Result<String> {
 pub struct Config {
     pub server_bind_address: String,
     pub api_key: String,
-    pub openai_api_key: String,
+    /// Only required when `embedding_backend` is `EmbeddingBackend::OpenAi`;
+    /// left unset for offline setups that embed via Ollama instead.
+    pub openai_api_key: Option<String>,
     pub preamble: String,
-    pub rag_directories: Vec<String>,
+    pub crawl: CrawlConfig,
+    pub embedding_backend: EmbeddingBackend,
+    pub ollama_base_url: String,
+    pub ollama_embedding_model: String,
+    pub ollama_embedding_dimensions: usize,
+    pub cache_dir: String,
+    pub rebuild_index: bool,
+    pub embedding_batch_size: usize,
+    /// Number of nearest neighbors retrieved before threshold/MMR filtering.
+    pub rag_k: usize,
+    /// Minimum similarity score a retrieved chunk must pass to be used.
+    pub rag_score_threshold: f64,
+    /// Trade-off between relevance and diversity in the MMR re-rank, in `[0, 1]`.
+    pub rag_mmr_lambda: f64,
 }
 
 impl Config {
     pub fn new() -> Self {
-        // Get directories for RAG system
-        let rag_directories = vec![
-            get_env_var(ENV_UNISWAP_DOCS_DIR_V2).expect("ENV_UNISWAP_DOCS_DIR_V2 not set"),
-            get_env_var(ENV_UNISWAP_DOCS_DIR_V3).expect("ENV_UNISWAP_DOCS_DIR_V3 not set"),
-            get_env_var(ENV_UNISWAP_SOURCE_DIR_V2).expect("ENV_UNISWAP_SOURCE_DIR_V2 not set"),
-            get_env_var(ENV_UNISWAP_SOURCE_DIR_V3).expect("ENV_UNISWAP_SOURCE_DIR_V3 not set"),
-        ];
+        // Legacy Uniswap directories, kept as the default crawl roots when
+        // `CRAWL_ROOTS` isn't set so existing deployments need no changes.
+        let legacy_rag_directories = [
+            ENV_UNISWAP_DOCS_DIR_V2,
+            ENV_UNISWAP_DOCS_DIR_V3,
+            ENV_UNISWAP_SOURCE_DIR_V2,
+            ENV_UNISWAP_SOURCE_DIR_V3,
+        ]
+        .into_iter()
+        .filter_map(|var| get_env_var(var).ok())
+        .collect();
+        let crawl = CrawlConfig::from_env(legacy_rag_directories);
+
+        // Backend for turning doc chunks into vectors; defaults to OpenAI so
+        // existing deployments keep working without setting anything new.
+        let embedding_backend = get_env_var(ENV_EMBEDDING_BACKEND)
+            .ok()
+            .map(|v| v.parse().expect("invalid EMBEDDING_BACKEND"))
+            .unwrap_or_default();
+
+        let ollama_base_url =
+            get_env_var(ENV_OLLAMA_BASE_URL).unwrap_or_else(|_| DEFAULT_OLLAMA_BASE_URL.into());
+        let ollama_embedding_model = get_env_var(ENV_OLLAMA_EMBEDDING_MODEL)
+            .unwrap_or_else(|_| DEFAULT_OLLAMA_EMBEDDING_MODEL.into());
+        let ollama_embedding_dimensions = get_env_var(ENV_OLLAMA_EMBEDDING_DIMENSIONS)
+            .ok()
+            .map(|v| v.parse().expect("invalid OLLAMA_EMBEDDING_DIMENSIONS"))
+            .unwrap_or(DEFAULT_OLLAMA_EMBEDDING_DIMENSIONS);
+
+        let cache_dir = get_env_var(ENV_CACHE_DIR).unwrap_or_else(|_| DEFAULT_CACHE_DIR.into());
+        let rebuild_index = rebuild_index_requested();
+        let embedding_batch_size = get_env_var(ENV_EMBEDDING_BATCH_SIZE)
+            .ok()
+            .map(|v| v.parse().expect("invalid EMBEDDING_BATCH_SIZE"))
+            .unwrap_or(DEFAULT_EMBEDDING_BATCH_SIZE);
+
+        let rag_k = get_env_var(ENV_RAG_K)
+            .ok()
+            .map(|v| v.parse().expect("invalid RAG_K"))
+            .unwrap_or(DEFAULT_RAG_K);
+        let rag_score_threshold = get_env_var(ENV_RAG_SCORE_THRESHOLD)
+            .ok()
+            .map(|v| v.parse().expect("invalid RAG_SCORE_THRESHOLD"))
+            .unwrap_or(DEFAULT_RAG_SCORE_THRESHOLD);
+        let rag_mmr_lambda = get_env_var(ENV_RAG_MMR_LAMBDA)
+            .ok()
+            .map(|v| v.parse().expect("invalid RAG_MMR_LAMBDA"))
+            .unwrap_or(DEFAULT_RAG_MMR_LAMBDA);
+
+        // Only the OpenAI backend needs an API key; Ollama setups are free
+        // to leave this unset entirely.
+        let openai_api_key = get_env_var(ENV_OPENAI_API_KEY).ok();
+        if embedding_backend == EmbeddingBackend::OpenAi && openai_api_key.is_none() {
+            panic!("failed to get openai api key: OPENAI_API_KEY must be set when EMBEDDING_BACKEND=openai");
+        }
 
         Self {
             server_bind_address: get_bind_address().expect("get bind address failed"),
             api_key: get_env_var(ENV_ANTHROPIC_API_KEY).expect("failed to get anthropic api key"),
-            openai_api_key: get_env_var(ENV_OPENAI_API_KEY).expect("failed to get openai api key"),
+            openai_api_key,
             preamble: get_env_var(ENV_RIG_PREAMBLE).expect("failed to set preamble"),
-            rag_directories,
+            crawl,
+            embedding_backend,
+            ollama_base_url,
+            ollama_embedding_model,
+            ollama_embedding_dimensions,
+            cache_dir,
+            rebuild_index,
+            embedding_batch_size,
+            rag_k,
+            rag_score_threshold,
+            rag_mmr_lambda,
         }
     }
 }
@@ -0,0 +1,111 @@
+//! Crawl configuration for `RAGBuilder::ingest_docs`.
+//!
+//! Generalizes ingestion from four hardcoded Uniswap directories into an
+//! arbitrary set of roots filtered by include/exclude globs, with a
+//! per-extension splitter and chunk size so the REPL can index any project
+//! tree, not just Markdown/Solidity docs.
+
+use std::collections::HashMap;
+
+use crate::common::get_env_var;
+
+const ENV_CRAWL_ROOTS: &str = "CRAWL_ROOTS";
+const ENV_CRAWL_INCLUDE_GLOBS: &str = "CRAWL_INCLUDE_GLOBS";
+const ENV_CRAWL_EXCLUDE_GLOBS: &str = "CRAWL_EXCLUDE_GLOBS";
+const ENV_CRAWL_ALL_FILES: &str = "CRAWL_ALL_FILES";
+const ENV_CRAWL_MAX_BYTES: &str = "CRAWL_MAX_CRAWL_BYTES";
+
+const DEFAULT_MAX_CRAWL_BYTES: u64 = 200 * 1024 * 1024;
+/// Chunk size used for files matched only via `all_files` (no extension entry).
+pub const DEFAULT_PLAIN_TEXT_CHUNK_SIZE: usize = 1000;
+
+/// Which splitter handles a given extension.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SplitterKind {
+    Markdown,
+    Solidity,
+    Rust,
+    TypeScript,
+    Json,
+    PlainText,
+}
+
+/// Splitter choice and chunk size for a single extension.
+#[derive(Clone, Copy, Debug)]
+pub struct ExtensionConfig {
+    pub splitter: SplitterKind,
+    pub chunk_size: usize,
+}
+
+/// Controls which files `ingest_docs` walks and how each is split.
+#[derive(Clone)]
+pub struct CrawlConfig {
+    pub roots: Vec<String>,
+    pub include_globs: Vec<String>,
+    pub exclude_globs: Vec<String>,
+    /// When true, extensions with no entry in `extensions` still get indexed
+    /// using a plain-text splitter instead of being skipped.
+    pub all_files: bool,
+    /// Ceiling on total bytes read during a single crawl, to bound memory.
+    pub max_crawl_bytes: u64,
+    pub extensions: HashMap<String, ExtensionConfig>,
+}
+
+impl CrawlConfig {
+    /// Builds the crawl config from `CRAWL_*` env vars, falling back to
+    /// `legacy_roots` (the four Uniswap directory env vars) when `CRAWL_ROOTS`
+    /// isn't set, so existing deployments don't need to change anything.
+    pub(crate) fn from_env(legacy_roots: Vec<String>) -> Self {
+        let roots = get_env_var(ENV_CRAWL_ROOTS)
+            .map(|v| split_csv(&v))
+            .unwrap_or(legacy_roots);
+        if roots.is_empty() {
+            tracing::warn!(
+                "no crawl roots configured (set CRAWL_ROOTS or one of the legacy UNISWAP_*_DIR_* vars); the RAG index will be empty"
+            );
+        }
+        let include_globs = get_env_var(ENV_CRAWL_INCLUDE_GLOBS)
+            .map(|v| split_csv(&v))
+            .unwrap_or_default();
+        let exclude_globs = get_env_var(ENV_CRAWL_EXCLUDE_GLOBS)
+            .map(|v| split_csv(&v))
+            .unwrap_or_default();
+        let all_files = get_env_var(ENV_CRAWL_ALL_FILES)
+            .ok()
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let max_crawl_bytes = get_env_var(ENV_CRAWL_MAX_BYTES)
+            .ok()
+            .map(|v| v.parse().expect("invalid CRAWL_MAX_CRAWL_BYTES"))
+            .unwrap_or(DEFAULT_MAX_CRAWL_BYTES);
+
+        Self {
+            roots,
+            include_globs,
+            exclude_globs,
+            all_files,
+            max_crawl_bytes,
+            extensions: default_extensions(),
+        }
+    }
+}
+
+fn default_extensions() -> HashMap<String, ExtensionConfig> {
+    use SplitterKind::*;
+    HashMap::from([
+        ("md".to_string(), ExtensionConfig { splitter: Markdown, chunk_size: 1000 }),
+        ("sol".to_string(), ExtensionConfig { splitter: Solidity, chunk_size: 1000 }),
+        ("rs".to_string(), ExtensionConfig { splitter: Rust, chunk_size: 1500 }),
+        ("ts".to_string(), ExtensionConfig { splitter: TypeScript, chunk_size: 1500 }),
+        ("tsx".to_string(), ExtensionConfig { splitter: TypeScript, chunk_size: 1500 }),
+        ("json".to_string(), ExtensionConfig { splitter: Json, chunk_size: 2000 }),
+    ])
+}
+
+fn split_csv(v: &str) -> Vec<String> {
+    v.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
@@ -0,0 +1,50 @@
+//! Disk-backed cache of chunk embeddings keyed by content hash.
+//!
+//! Re-embedding every doc on every launch is the dominant cost of a cold
+//! start. Each `UniswapChunk` is keyed by `blake3(path + content)`, so
+//! `RAGBuilder::build` only has to send cache misses to the embedding
+//! provider and a run with no doc changes costs nothing but disk reads.
+
+use std::path::Path;
+
+/// Computes the cache key for a chunk from its source name and content.
+pub fn chunk_key(file_name: &str, content: &str) -> String {
+    blake3::hash(format!("{file_name}{content}").as_bytes())
+        .to_hex()
+        .to_string()
+}
+
+/// A sled-backed key/value store mapping chunk keys to their embedding
+/// vectors.
+pub struct EmbeddingCache {
+    db: sled::Db,
+}
+
+impl EmbeddingCache {
+    /// Opens (creating if needed) the cache rooted at `cache_dir`.
+    pub fn open(cache_dir: &str) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(cache_dir)?;
+        let db = sled::open(Path::new(cache_dir).join("embeddings.sled"))?;
+        Ok(Self { db })
+    }
+
+    /// Looks up a previously-stored embedding for `key`.
+    pub fn get(&self, key: &str) -> anyhow::Result<Option<Vec<f32>>> {
+        match self.db.get(key)? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores `vector` for `key`, overwriting any existing entry.
+    pub fn put(&self, key: &str, vector: &[f32]) -> anyhow::Result<()> {
+        self.db.insert(key, bincode::serialize(vector)?)?;
+        Ok(())
+    }
+
+    /// Clears every cached embedding. Used by the `--rebuild-index` flag.
+    pub fn clear(&self) -> anyhow::Result<()> {
+        self.db.clear()?;
+        Ok(())
+    }
+}
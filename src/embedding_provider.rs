@@ -0,0 +1,268 @@
+//! Pluggable embedding backends for the RAG pipeline.
+//!
+//! `RAGBuilder` no longer talks to OpenAI directly; instead it holds a boxed
+//! [`EmbeddingProvider`] selected at startup from `Config::embedding_backend`.
+//! This keeps the ingestion/build pipeline in `rag_builder` agnostic to which
+//! backend actually produces the vectors, so offline setups can point at a
+//! local Ollama instance instead of requiring an OpenAI API key.
+
+use anyhow::Context;
+use rig::prelude::EmbeddingsClient;
+use rig::providers::openai::client::Client as OpenAiClient;
+use rig::providers::openai::{EmbeddingModel as OpenAiEmbeddingModel, TEXT_EMBEDDING_ADA_002};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+use crate::common::Config;
+
+const MAX_EMBED_RETRIES: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Backend selected via `Config::embedding_backend`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum EmbeddingBackend {
+    #[default]
+    OpenAi,
+    Ollama,
+}
+
+impl std::str::FromStr for EmbeddingBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "openai" => Ok(Self::OpenAi),
+            "ollama" => Ok(Self::Ollama),
+            other => anyhow::bail!("unknown embedding backend: {other}"),
+        }
+    }
+}
+
+/// Boxed future returned by [`EmbeddingProvider::embed`]; pinning it
+/// explicitly (rather than `async fn` in the trait) keeps the trait
+/// dyn-compatible so `Box<dyn EmbeddingProvider>`/`Arc<dyn EmbeddingProvider>`
+/// can be passed around without knowing the concrete backend.
+pub type EmbedFuture<'a> = Pin<Box<dyn Future<Output = anyhow::Result<Vec<Vec<f32>>>> + Send + 'a>>;
+
+/// A backend capable of turning raw text into embedding vectors.
+///
+/// Implementations are free to batch, cache, or hit the network however they
+/// like; callers only rely on `embed` returning one vector per input text, in
+/// order, and `dimensions` matching the length of those vectors.
+pub trait EmbeddingProvider: Send + Sync {
+    fn embed(&self, texts: Vec<String>) -> EmbedFuture<'_>;
+
+    fn dimensions(&self) -> usize;
+}
+
+/// Wraps the existing OpenAI embeddings path.
+pub struct OpenAiProvider {
+    model: OpenAiEmbeddingModel,
+}
+
+impl OpenAiProvider {
+    pub fn new(cfg: &Config) -> Self {
+        let api_key = cfg
+            .openai_api_key
+            .as_deref()
+            .expect("OpenAiProvider requires Config::openai_api_key to be set");
+        let client = OpenAiClient::new(api_key);
+        let model = client.embedding_model(TEXT_EMBEDDING_ADA_002);
+        Self { model }
+    }
+}
+
+impl EmbeddingProvider for OpenAiProvider {
+    fn embed(&self, texts: Vec<String>) -> EmbedFuture<'_> {
+        use rig::embeddings::embedding::EmbeddingModel;
+
+        Box::pin(async move {
+            let embeddings = self.model.embed_texts(texts).await?;
+            Ok(embeddings
+                .into_iter()
+                .map(|e| e.vec.into_iter().map(|v| v as f32).collect())
+                .collect())
+        })
+    }
+
+    fn dimensions(&self) -> usize {
+        use rig::embeddings::embedding::EmbeddingModel;
+        self.model.ndims()
+    }
+}
+
+#[derive(Serialize)]
+struct OllamaEmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Talks to a local Ollama `/api/embeddings` endpoint.
+pub struct OllamaProvider {
+    base_url: String,
+    model: String,
+    dimensions: usize,
+    http: reqwest::Client,
+}
+
+impl OllamaProvider {
+    pub fn new(cfg: &Config) -> Self {
+        Self {
+            base_url: cfg.ollama_base_url.clone(),
+            model: cfg.ollama_embedding_model.clone(),
+            dimensions: cfg.ollama_embedding_dimensions,
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+impl EmbeddingProvider for OllamaProvider {
+    fn embed(&self, texts: Vec<String>) -> EmbedFuture<'_> {
+        Box::pin(async move {
+            let url = format!("{}/api/embeddings", self.base_url);
+            let req = OllamaEmbedRequest {
+                model: &self.model,
+                input: &texts,
+            };
+
+            let res = self
+                .http
+                .post(&url)
+                .json(&req)
+                .send()
+                .await
+                .context("failed to reach Ollama embeddings endpoint")?
+                .error_for_status()
+                .context("Ollama embeddings endpoint returned an error")?
+                .json::<OllamaEmbedResponse>()
+                .await
+                .context("failed to parse Ollama embeddings response")?;
+
+            Ok(res.embeddings)
+        })
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+}
+
+/// Builds the provider selected by `cfg.embedding_backend`.
+pub fn build_provider(cfg: &Config) -> Box<dyn EmbeddingProvider> {
+    match cfg.embedding_backend {
+        EmbeddingBackend::OpenAi => Box::new(OpenAiProvider::new(cfg)),
+        EmbeddingBackend::Ollama => Box::new(OllamaProvider::new(cfg)),
+    }
+}
+
+/// Embeds `texts` in fixed-size batches (`Config::embedding_batch_size`),
+/// running up to `max_concurrent` batches at once behind a semaphore and
+/// retrying individual batches with exponential backoff when the provider
+/// reports rate limiting, so a large corpus doesn't blow past the
+/// provider's per-request batch limit or rate cap.
+///
+/// Progress is reported through a `tracing` span (`chunks embedded / total`)
+/// so large ingests stay observable.
+pub async fn embed_batched(
+    provider: Arc<dyn EmbeddingProvider>,
+    texts: Vec<String>,
+    batch_size: usize,
+    max_concurrent: usize,
+) -> anyhow::Result<Vec<Vec<f32>>> {
+    let total = texts.len();
+    let span = tracing::info_span!("embed_batched", total);
+    let _enter = span.enter();
+
+    let semaphore = Arc::new(Semaphore::new(max_concurrent.max(1)));
+    let embedded = Arc::new(AtomicUsize::new(0));
+    let mut tasks = tokio::task::JoinSet::new();
+
+    let batches: Vec<Vec<String>> = texts
+        .chunks(batch_size.max(1))
+        .map(|batch| batch.to_vec())
+        .collect();
+
+    for (batch_index, batch) in batches.into_iter().enumerate() {
+        let provider = provider.clone();
+        let semaphore = semaphore.clone();
+        let embedded = embedded.clone();
+        let batch_len = batch.len();
+
+        tasks.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("embedding semaphore is never closed");
+            let result = embed_with_backoff(provider.as_ref(), batch).await;
+            if result.is_ok() {
+                let done = embedded.fetch_add(batch_len, Ordering::Relaxed) + batch_len;
+                tracing::info!(done, total, "embedded chunks");
+            }
+            (batch_index, result)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let (batch_index, result) = joined.context("embedding batch task panicked")?;
+        results.push((batch_index, result?));
+    }
+
+    results.sort_by_key(|(index, _)| *index);
+    Ok(results.into_iter().flat_map(|(_, vectors)| vectors).collect())
+}
+
+/// Embeds a single batch, retrying with exponential backoff when the
+/// provider reports rate limiting (HTTP 429).
+async fn embed_with_backoff(
+    provider: &dyn EmbeddingProvider,
+    batch: Vec<String>,
+) -> anyhow::Result<Vec<Vec<f32>>> {
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 0..MAX_EMBED_RETRIES {
+        match provider.embed(batch.clone()).await {
+            Ok(vectors) => return Ok(vectors),
+            Err(err) if is_rate_limited(&err) && attempt + 1 < MAX_EMBED_RETRIES => {
+                tracing::warn!(
+                    attempt,
+                    backoff_ms = backoff.as_millis() as u64,
+                    "embedding request rate limited, retrying"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!("loop above always returns by the final attempt")
+}
+
+fn is_rate_limited(err: &anyhow::Error) -> bool {
+    // `{:#}` includes the whole error chain, so a 429 status wrapped under
+    // an added context message is still seen.
+    format!("{err:#}").contains("429")
+}
+
+/// Normalizes `vec` to unit length in place.
+///
+/// Different backends emit different dimensionalities and magnitudes; every
+/// vector is normalized before it is stored so that cosine/dot-product
+/// comparisons stay consistent no matter which provider produced them.
+pub fn normalize(vec: &mut [f32]) {
+    let norm = vec.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for v in vec.iter_mut() {
+            *v /= norm;
+        }
+    }
+}
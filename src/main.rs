@@ -8,11 +8,17 @@
 //!
 //! Modules:
 //! - `common`: Shared configuration and utilities.
+//! - `crawl`: Configurable file crawl (roots, glob filters, per-extension splitters).
+//! - `embedding_cache`: Disk-backed, content-hash-keyed embedding cache.
+//! - `embedding_provider`: Pluggable embedding backends (OpenAI, Ollama).
 //! - `rag_builder`: RAG (retrieval-augmented generation) data preparation.
 //! - `rig_agent`: Agent logic for handling REPL interactions.
 
 
 mod common;
+mod crawl;
+mod embedding_cache;
+mod embedding_provider;
 mod rag_builder;
 mod rig_agent;
 mod rag_middleware;
@@ -33,8 +39,6 @@ async fn main() -> anyhow::Result<()> {
         .with_ansi(false)
         .init();
 
-    RigAgent::new(Config::new(), anthropic::CLAUDE_3_5_SONNET)
-        .await?
-        .start_repl()
-        .await
+    let mut agent = RigAgent::new(Config::new(), anthropic::CLAUDE_3_5_SONNET).await?;
+    agent.start_repl().await
 }
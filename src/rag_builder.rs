@@ -1,46 +1,129 @@
 //! RAG (Retrieval-Augmented Generation) builder module.
 //!
-//! Provides functionality to ingest and process documentation files,
-//! specifically Markdown (`.md`) and Solidity (`.sol`) sources,
-//! and build an in-memory vector index for use with OpenAI embeddings.
+//! Provides functionality to ingest and process documentation and source
+//! files from a configurable crawl (see [`crate::crawl`]) and build an
+//! in-memory vector index via a pluggable embedding provider.
 //!
 //! This module enables the creation of a searchable knowledge base by:
-//! - Walking configured directories to locate relevant files.
-//! - Splitting file contents into manageable chunks while preserving structure.
-//! - Embedding chunks into vector representations using OpenAI's embedding models.
+//! - Walking the configured crawl roots, filtered by include/exclude globs.
+//! - Splitting each file into manageable chunks with the splitter configured
+//!   for its extension, preserving structure (markdown headers, code syntax).
+//! - Embedding chunks into vector representations, reusing cached vectors for
+//!   chunks that haven't changed since the last run.
 //!
 //! The resulting vector index supports retrieval tasks for enhanced language model context.
 
 use crate::common::Config;
+use crate::crawl::{CrawlConfig, ExtensionConfig, SplitterKind, DEFAULT_PLAIN_TEXT_CHUNK_SIZE};
+use crate::embedding_cache::{self, EmbeddingCache};
+use crate::embedding_provider::{self, EmbeddingProvider};
 
-use rig::prelude::EmbeddingsClient;
-use rig::providers::openai::client::Client;
-use rig::providers::openai::{EmbeddingModel, TEXT_EMBEDDING_ADA_002};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rig::OneOrMany;
+use rig::embeddings::embedding::{Embedding, EmbeddingError};
 use rig::vector_store::in_memory_store::{InMemoryVectorIndex, InMemoryVectorStore};
-use rig::{Embed, embeddings::EmbeddingsBuilder};
+use rig::Embed;
 use serde::{Serialize, Deserialize};
 use std::fs;
-use text_splitter::CodeSplitter;
+use std::sync::Arc;
+use text_splitter::{ChunkConfig, CodeSplitter, MarkdownSplitter, TextSplitter};
 use walkdir::WalkDir;
 
-const MD_EXTENSION: &str = "md";
-const SOL_EXTENSION: &str = "sol";
+/// Number of concurrent batches in flight at once; bounds the load a large
+/// ingest puts on the embedding provider independent of the batch size.
+const EMBED_MAX_CONCURRENT_BATCHES: usize = 4;
 
-/// Represents a file with its data indexed into segments
-#[derive(Embed, Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Default)]
+/// Represents a chunk of a source file, along with where in that file it
+/// came from so retrieval results can be cited back to their origin.
+#[derive(Embed, Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
 pub struct UniswapChunk {
-    file_name: String,
+    pub(crate) path: String,
+    pub(crate) start_byte: usize,
+    pub(crate) end_byte: usize,
+    /// Enclosing markdown heading trail (outermost first). Empty for
+    /// non-markdown chunks.
+    pub(crate) heading_trail: Vec<String>,
+    /// Unit-normalized embedding vector, stored alongside the chunk so
+    /// retrieval-time MMR re-ranking can compare candidates without
+    /// re-embedding anything. Empty until `RAGBuilder::build` fills it in.
+    #[serde(default)]
+    pub(crate) vector: Vec<f32>,
     #[embed]
     pub(crate) content: String,
 }
 
 impl UniswapChunk {
-    fn new(file_name: &str) -> Self {
+    fn new(path: &str, start_byte: usize, end_byte: usize, content: &str) -> Self {
         Self {
-            file_name: String::from(file_name),
-            content: String::new(),
+            path: String::from(path),
+            start_byte,
+            end_byte,
+            heading_trail: Vec::new(),
+            vector: Vec::new(),
+            content: String::from(content),
         }
     }
+
+    /// Renders the `path:start-end` citation used in RAG context and the
+    /// REPL's "Sources:" footer, appending the enclosing markdown heading
+    /// trail (e.g. `docs/foo.md:120-340 (Pools > Creating a pool)`) when the
+    /// chunk has one.
+    pub(crate) fn citation(&self) -> String {
+        let base = format!("{}:{}-{}", self.path, self.start_byte, self.end_byte);
+        if self.heading_trail.is_empty() {
+            base
+        } else {
+            format!("{base} ({})", self.heading_trail.join(" > "))
+        }
+    }
+}
+
+/// Adapts a boxed [`EmbeddingProvider`] to rig's own `EmbeddingModel` trait so
+/// it can be plugged into `EmbeddingsBuilder` and `InMemoryVectorIndex`
+/// unchanged, regardless of which backend is actually producing vectors.
+#[derive(Clone)]
+pub(crate) struct ProviderEmbeddingModel {
+    provider: Arc<dyn EmbeddingProvider>,
+}
+
+impl ProviderEmbeddingModel {
+    fn new(provider: Arc<dyn EmbeddingProvider>) -> Self {
+        Self { provider }
+    }
+}
+
+impl rig::embeddings::embedding::EmbeddingModel for ProviderEmbeddingModel {
+    const MAX_DOCUMENTS: usize = 1024;
+
+    fn ndims(&self) -> usize {
+        self.provider.dimensions()
+    }
+
+    async fn embed_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        let texts: Vec<String> = texts.into_iter().collect();
+        let documents = texts.clone();
+
+        let mut vectors = self
+            .provider
+            .embed(texts)
+            .await
+            .map_err(|e| EmbeddingError::ProviderError(e.to_string()))?;
+
+        Ok(documents
+            .into_iter()
+            .zip(vectors.iter_mut())
+            .map(|(document, vec)| {
+                embedding_provider::normalize(vec);
+                Embedding {
+                    document,
+                    vec: vec.iter().map(|v| *v as f64).collect(),
+                }
+            })
+            .collect())
+    }
 }
 
 /// Builder for retrieval-augmented generation (RAG) vector index
@@ -48,38 +131,102 @@ impl UniswapChunk {
 pub struct RAGBuilder {
     docs: Vec<UniswapChunk>,
     cfg: Config,
+    provider: Arc<dyn EmbeddingProvider>,
 }
 
 impl RAGBuilder {
     pub fn new(cfg: Config) -> Self {
+        let provider: Arc<dyn EmbeddingProvider> = Arc::from(embedding_provider::build_provider(&cfg));
         Self {
             docs: Vec::new(),
             cfg,
+            provider,
         }
     }
 
-    /// Builds an in-memory vector index using OpenAI embeddings
-    /// from the ingested documents.
-    pub async fn build(self) -> anyhow::Result<InMemoryVectorIndex<EmbeddingModel, UniswapChunk>> {
+    /// Builds an in-memory vector index using the configured embedding
+    /// provider (OpenAI or Ollama) from the ingested documents.
+    ///
+    /// Each chunk is looked up in the on-disk [`EmbeddingCache`] first;
+    /// only cache misses are sent to the embedding provider, which turns a
+    /// cold start into an O(changed-docs) job instead of O(all-docs).
+    pub async fn build(
+        self,
+    ) -> anyhow::Result<InMemoryVectorIndex<ProviderEmbeddingModel, UniswapChunk>> {
         tracing::info!("Setting up Vector Index for Uniswap docs");
-        // Create OpenAI client
-        let openai_api_key = self.cfg.openai_api_key.clone();
-        let client = Client::new(&openai_api_key);
-        let embedding_model = client.embedding_model(TEXT_EMBEDDING_ADA_002);
-
-        let embeddings = EmbeddingsBuilder::new(embedding_model.clone())
-            .documents(self.docs)?
-            .build()
+
+        let cache = EmbeddingCache::open(&self.cfg.cache_dir)?;
+        if self.cfg.rebuild_index {
+            tracing::info!("--rebuild-index passed, clearing embedding cache");
+            cache.clear()?;
+        }
+
+        let keys: Vec<String> = self
+            .docs
+            .iter()
+            .map(|doc| embedding_cache::chunk_key(&doc.path, &doc.content))
+            .collect();
+
+        let mut vectors: Vec<Option<Vec<f32>>> = Vec::with_capacity(self.docs.len());
+        let mut miss_indices = Vec::new();
+        let mut miss_texts = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            match cache.get(key)? {
+                Some(vec) => vectors.push(Some(vec)),
+                None => {
+                    vectors.push(None);
+                    miss_indices.push(i);
+                    miss_texts.push(self.docs[i].content.clone());
+                }
+            }
+        }
+
+        if !miss_texts.is_empty() {
+            tracing::info!(
+                "Embedding {} of {} chunks (cache hit on the rest)",
+                miss_texts.len(),
+                self.docs.len()
+            );
+            let mut embedded = embedding_provider::embed_batched(
+                self.provider.clone(),
+                miss_texts,
+                self.cfg.embedding_batch_size,
+                EMBED_MAX_CONCURRENT_BATCHES,
+            )
             .await?;
+            for (vec, &i) in embedded.iter_mut().zip(miss_indices.iter()) {
+                embedding_provider::normalize(vec);
+                cache.put(&keys[i], vec)?;
+                vectors[i] = Some(vec.clone());
+            }
+        }
 
-        let vector_store = InMemoryVectorStore::from_documents(embeddings);
+        let documents: Vec<(UniswapChunk, OneOrMany<Embedding>)> = self
+            .docs
+            .into_iter()
+            .zip(vectors)
+            .map(|(mut doc, vec)| {
+                let vec = vec.expect("every chunk is either a cache hit or freshly embedded");
+                doc.vector = vec.clone();
+                let embedding = Embedding {
+                    document: doc.content.clone(),
+                    vec: vec.into_iter().map(|v| v as f64).collect(),
+                };
+                (doc, OneOrMany::one(embedding))
+            })
+            .collect();
+
+        let embedding_model = ProviderEmbeddingModel::new(self.provider);
+        let vector_store = InMemoryVectorStore::from_documents(documents);
         let index = vector_store.index(embedding_model);
 
         Ok(index)
     }
 
-    /// Walks through configured directories and ingests relevant `.md` and `.sol` files,
-    /// returning an updated builder for chaining.
+    /// Walks the configured crawl roots, filtering by include/exclude globs
+    /// and bounding total bytes read by `max_crawl_bytes`, and ingests every
+    /// file whose extension is configured (or, when `all_files` is set, every
+    /// file at all) using the splitter configured for its extension.
     ///
     /// # Example
     /// ```
@@ -89,66 +236,157 @@ impl RAGBuilder {
     ///     .await?;
     /// ```
     pub fn ingest_docs(mut self) -> anyhow::Result<Self> {
-        tracing::debug!("Ingesting Uniswap docs and source code");
-        // Walk through each directory and process relevant files
-        for dir in self.cfg.rag_directories.clone() {
-            for file in WalkDir::new(dir)
+        tracing::debug!("Ingesting configured crawl roots");
+        let crawl = self.cfg.crawl.clone();
+        let include_set = build_globset(&crawl.include_globs)?;
+        let exclude_set = build_globset(&crawl.exclude_globs)?;
+
+        let mut crawled_bytes: u64 = 0;
+        'roots: for root in &crawl.roots {
+            for file in WalkDir::new(root)
                 .into_iter()
                 .filter_map(Result::ok)
                 .filter(|e| e.file_type().is_file())
             {
                 let path = file.path();
-                let name = format!("{:?}", file.file_name());
-                tracing::debug!("Ingesting file: {name}");
 
-                match file.path().extension().and_then(|ext| ext.to_str()) {
-                    // Only read the file if matches with one of the extensions
-                    Some(MD_EXTENSION) => {
-                        let file_str = fs::read_to_string(path)?;
-                        self = self.ingest_md_file(file_str, name.clone())?
-                    }
-                    Some(SOL_EXTENSION) => {
-                        let file_str = fs::read_to_string(path)?;
-                        self = self.ingest_solidity_file(file_str, name)?
-                    }
-                    _ => {
-                        // Ignore other files for now
-                    }
+                if include_set.as_ref().is_some_and(|set| !set.is_match(path)) {
+                    continue;
+                }
+                if exclude_set.as_ref().is_some_and(|set| set.is_match(path)) {
+                    continue;
+                }
+
+                let Some(ext_cfg) = extension_config(&crawl, path) else {
+                    continue;
+                };
+
+                let file_len = file.metadata().map(|m| m.len()).unwrap_or(0);
+                if crawled_bytes + file_len > crawl.max_crawl_bytes {
+                    tracing::warn!(
+                        max_crawl_bytes = crawl.max_crawl_bytes,
+                        "max_crawl_bytes reached, stopping crawl"
+                    );
+                    break 'roots;
                 }
+                crawled_bytes += file_len;
+
+                let name = path.to_string_lossy().into_owned();
+                let file_str = match fs::read_to_string(path) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        tracing::debug!("Skipping {name}, not readable as UTF-8: {e}");
+                        continue;
+                    }
+                };
+
+                tracing::debug!("Ingesting file: {name}");
+                self = self.ingest_file(file_str, name, ext_cfg)?;
             }
         }
         Ok(self)
     }
 
-    /// Processes a Markdown file by splitting it into chunks
-    /// and adding them to the document content.
-    fn ingest_md_file(mut self, file: String, name: String) -> anyhow::Result<Self> {
-        let splitter = text_splitter::MarkdownSplitter::new(text_splitter::ChunkConfig::new(1000));
+    /// Splits `file` using the splitter configured for its extension and adds
+    /// the resulting chunks to `self.docs`.
+    fn ingest_file(mut self, file: String, name: String, ext_cfg: ExtensionConfig) -> anyhow::Result<Self> {
+        let chunk_config = ChunkConfig::new(ext_cfg.chunk_size);
+        let chunks: Vec<(usize, &str)> = match ext_cfg.splitter {
+            SplitterKind::Markdown => {
+                MarkdownSplitter::new(chunk_config).chunk_indices(&file).collect()
+            }
+            SplitterKind::Solidity => {
+                CodeSplitter::new(tree_sitter_solidity::LANGUAGE, chunk_config)?
+                    .chunk_indices(&file)
+                    .collect()
+            }
+            SplitterKind::Rust => {
+                CodeSplitter::new(tree_sitter_rust::LANGUAGE, chunk_config)?
+                    .chunk_indices(&file)
+                    .collect()
+            }
+            SplitterKind::TypeScript => {
+                CodeSplitter::new(tree_sitter_typescript::LANGUAGE_TYPESCRIPT, chunk_config)?
+                    .chunk_indices(&file)
+                    .collect()
+            }
+            SplitterKind::Json => {
+                CodeSplitter::new(tree_sitter_json::LANGUAGE, chunk_config)?
+                    .chunk_indices(&file)
+                    .collect()
+            }
+            SplitterKind::PlainText => {
+                TextSplitter::new(chunk_config).chunk_indices(&file).collect()
+            }
+        };
 
-        // Using a text splitter specifically for markdown files to maintain headers and other tags
-        for chunk in splitter.chunks(&file) {
-            let mut doc = UniswapChunk::new(&name);
-            doc.content = String::from(chunk);
+        for (start_byte, chunk) in chunks {
+            let mut doc = UniswapChunk::new(&name, start_byte, start_byte + chunk.len(), chunk);
+            if ext_cfg.splitter == SplitterKind::Markdown {
+                doc.heading_trail = heading_trail_at(&file, start_byte);
+            }
             self.docs.push(doc);
         }
 
         Ok(self)
     }
+}
 
-    /// Processes a Solidity source file by splitting it into code chunks
-    /// using a language-aware splitter and adding them to the document content.
-    fn ingest_solidity_file(mut self, file: String, name: String) -> anyhow::Result<Self> {
-        let code_splitter = CodeSplitter::new(
-            tree_sitter_solidity::LANGUAGE,
-            text_splitter::ChunkConfig::new(1000),
-        )?;
-
-        for chunk in code_splitter.chunks(&file) {
-            let mut doc = UniswapChunk::new(&name);
-            doc.content = String::from(chunk);
-            self.docs.push(doc);
+/// Looks up the splitter/chunk-size for `path`'s extension, falling back to
+/// a plain-text splitter when `crawl.all_files` is set and the extension has
+/// no dedicated entry, or `None` to skip the file entirely.
+fn extension_config(crawl: &CrawlConfig, path: &std::path::Path) -> Option<ExtensionConfig> {
+    let ext = path.extension().and_then(|e| e.to_str());
+    if let Some(cfg) = ext.and_then(|ext| crawl.extensions.get(ext)) {
+        return Some(*cfg);
+    }
+    crawl.all_files.then_some(ExtensionConfig {
+        splitter: SplitterKind::PlainText,
+        chunk_size: DEFAULT_PLAIN_TEXT_CHUNK_SIZE,
+    })
+}
+
+fn build_globset(patterns: &[String]) -> anyhow::Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+/// Walks the markdown headings preceding `start_byte` and returns the
+/// enclosing heading trail (outermost first), e.g. `["Pools", "Creating a
+/// pool"]` for a chunk nested under an `## Creating a pool` sub-heading of
+/// a `# Pools` section.
+fn heading_trail_at(file: &str, start_byte: usize) -> Vec<String> {
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut offset = 0;
+
+    for line in file.split_inclusive('\n') {
+        if offset >= start_byte {
+            break;
+        }
+        let trimmed = line.trim_end_matches('\n');
+        if let Some(level) = atx_heading_level(trimmed) {
+            let text = trimmed.trim_start_matches('#').trim().to_string();
+            stack.retain(|(l, _)| *l < level);
+            stack.push((level, text));
         }
+        offset += line.len();
+    }
 
-        Ok(self)
+    stack.into_iter().map(|(_, text)| text).collect()
+}
+
+/// Returns the heading level (1-6) if `line` is an ATX markdown heading.
+fn atx_heading_level(line: &str) -> Option<usize> {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&hashes) && line.as_bytes().get(hashes) == Some(&b' ') {
+        Some(hashes)
+    } else {
+        None
     }
 }
@@ -3,43 +3,102 @@ use crate::rag_builder::UniswapChunk;
 use crate::rig_agent::RigAgent;
 
 const SEPARATOR: &str = "\n\n---\n\n";
-const DISTANCE: f64 = 0.9;
 const MAX_CHAR_LEN: usize = 30000;
 
 pub trait RagMiddleware {
-    async fn query_rag(&mut self, prompt: &str) -> anyhow::Result<String>;
+    /// Returns the query augmented with retrieved context, along with the
+    /// `path:start-end` citation of each chunk that was used, in selection
+    /// order (not deduplicated; `RigAgent::display_response` does that).
+    async fn query_rag(&mut self, prompt: &str) -> anyhow::Result<(String, Vec<String>)>;
 }
 
 impl RagMiddleware for RigAgent {
-    async fn query_rag(&mut self, query: &str) -> anyhow::Result<String> {
-        let req = VectorSearchRequest::builder().query(query).samples(30).build()?;
-        let search_results: Vec<(f64, String, UniswapChunk)> =  self.index.top_n(req).await?;
+    async fn query_rag(&mut self, query: &str) -> anyhow::Result<(String, Vec<String>)> {
+        let req = VectorSearchRequest::builder()
+            .query(query)
+            .samples(self.rag_k as u64)
+            .build()?;
+        let search_results: Vec<(f64, String, UniswapChunk)> = self.index.top_n(req).await?;
+
+        // Keep only chunks whose similarity clears the configured threshold.
+        let candidates: Vec<(f64, UniswapChunk)> = search_results
+            .into_iter()
+            .filter(|(score, _, _)| *score >= self.rag_score_threshold)
+            .map(|(score, _, chunk)| (score, chunk))
+            .collect();
 
         // If there were no relevant results, just leave the original query
-        if search_results.is_empty() {
-            return Ok(String::from(query));
+        if candidates.is_empty() {
+            return Ok((String::from(query), Vec::new()));
         }
 
-        // Clear history before processing
-        self.history.clear();
-
-        let mut ctx_size = 0;
-        let mut context = Vec::new();
-        for (score, name, chunk) in search_results {
-            if score > 0.9 {
-                continue;
-            }
-            if chunk.content.len() + ctx_size > MAX_CHAR_LEN {
-                continue;
-            }
-            ctx_size += chunk.content.len();
-            context.push(format!("Source: {}\nContent: {}", name, chunk.content));
+        let selected = mmr_select(candidates, self.rag_mmr_lambda, MAX_CHAR_LEN);
+
+        let mut context = Vec::with_capacity(selected.len());
+        let mut sources = Vec::with_capacity(selected.len());
+        for chunk in &selected {
+            let citation = chunk.citation();
+            context.push(format!("Source: {citation}\nContent: {}", chunk.content));
+            sources.push(citation);
         }
 
         // Consolidate chunks
         let context = context.join(SEPARATOR);
 
         // Attach relevant information to the query for the Agent to use
-        Ok(format!("You have access to the following relevant documentation: \n\n{context}\n\n --- \n\nUser: {query}"))
+        let query = format!("You have access to the following relevant documentation: \n\n{context}\n\n --- \n\nUser: {query}");
+        Ok((query, sources))
+    }
+}
+
+/// Greedily selects chunks via maximal-marginal-relevance so near-duplicate
+/// chunks from the same file don't crowd out diverse context: the highest
+/// scoring chunk is taken first, then each subsequent pick maximizes
+/// `lambda * relevance - (1 - lambda) * max_similarity_to_selected`, using
+/// the chunks' stored unit vectors' dot product as similarity. Candidates
+/// that would overflow `max_char_len` are skipped rather than stopping the
+/// pack, so a large chunk winning an early round doesn't crowd out smaller
+/// ones still in `remaining`.
+fn mmr_select(
+    mut remaining: Vec<(f64, UniswapChunk)>,
+    lambda: f64,
+    max_char_len: usize,
+) -> Vec<UniswapChunk> {
+    let mut selected: Vec<UniswapChunk> = Vec::new();
+    let mut used_chars = 0usize;
+
+    while !remaining.is_empty() {
+        let best = remaining
+            .iter()
+            .enumerate()
+            .map(|(i, (relevance, chunk))| {
+                let max_similarity_to_selected = selected
+                    .iter()
+                    .map(|s| dot(&chunk.vector, &s.vector) as f64)
+                    .fold(f64::MIN, f64::max);
+                let max_similarity_to_selected = if selected.is_empty() {
+                    0.0
+                } else {
+                    max_similarity_to_selected
+                };
+                let mmr_score = lambda * relevance - (1.0 - lambda) * max_similarity_to_selected;
+                (i, mmr_score)
+            })
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, _)| i)
+            .expect("remaining is non-empty");
+
+        let (_, chunk) = remaining.remove(best);
+        if used_chars + chunk.content.len() > max_char_len {
+            continue;
+        }
+        used_chars += chunk.content.len();
+        selected.push(chunk);
     }
-}
\ No newline at end of file
+
+    selected
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
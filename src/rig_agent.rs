@@ -28,14 +28,14 @@ use rig::client::CompletionClient;
 use rig::completion::{Prompt, PromptError};
 use rig::message::Message;
 use rig::providers::anthropic;
-use rig::providers::openai::EmbeddingModel;
 use rig::vector_store::in_memory_store::InMemoryVectorIndex;
 use rustyline::DefaultEditor;
 
 use crate::common::Config;
 use crate::rag_builder::{
+    ProviderEmbeddingModel,
     RAGBuilder,
-    UniswapDoc,
+    UniswapChunk,
 };
 use crate::rag_middleware::RagMiddleware;
 
@@ -43,7 +43,12 @@ const PROCESSING_MESSAGE: &str = "Claude: Processing Request...";
 
 pub struct RigAgent {
     agent: Agent<anthropic::completion::CompletionModel>,
-    pub(crate) index: InMemoryVectorIndex<EmbeddingModel, UniswapDoc>,
+    pub(crate) index: InMemoryVectorIndex<ProviderEmbeddingModel, UniswapChunk>,
+    /// Multi-turn conversation history, threaded through every prompt.
+    history: Vec<Message>,
+    pub(crate) rag_k: usize,
+    pub(crate) rag_score_threshold: f64,
+    pub(crate) rag_mmr_lambda: f64,
 }
 
 /// Implement Rig Agent
@@ -53,7 +58,10 @@ impl RigAgent {
     /// Builds an Anthropic client with the given API key and model,
     /// sets up a RAG vector index from local docs,
     /// connects to the MCP server to load available tools,
-    /// and configures the agent with preamble and dynamic context.
+    /// and configures the agent with its preamble. Retrieval itself happens
+    /// per-turn in [`crate::rag_middleware::RagMiddleware::query_rag`], which
+    /// threshold-filters and MMR-selects chunks from this same index before
+    /// the query reaches the agent.
     pub async fn new(cfg: Config, model: &str) -> anyhow::Result<Self> {
         tracing::info!("Creating new Agent");
         let client = anthropic::ClientBuilder::new(&cfg.api_key)
@@ -79,12 +87,16 @@ impl RigAgent {
                 builder.mcp_tool(tool, mcp_client.clone())
             });
 
-        let agent = agent_builder
-            .preamble(&cfg.preamble)
-            //.dynamic_context(3, index)
-            .build();
+        let agent = agent_builder.preamble(&cfg.preamble).build();
 
-        Ok(Self { agent, index })
+        Ok(Self {
+            agent,
+            index,
+            history: Vec::new(),
+            rag_k: cfg.rag_k,
+            rag_score_threshold: cfg.rag_score_threshold,
+            rag_mmr_lambda: cfg.rag_mmr_lambda,
+        })
     }
 
     /// Starts the interactive REPL loop.
@@ -92,10 +104,9 @@ impl RigAgent {
     /// Reads user input lines, sends prompts to the agent,
     /// handles multi-turn conversations with tool usage,
     /// and displays responses or errors.
-    pub async fn start_repl(&self) -> anyhow::Result<()> {
+    pub async fn start_repl(&mut self) -> anyhow::Result<()> {
         tracing::info!("Starting interactive REPL...");
         let mut rl = DefaultEditor::new()?;
-        let mut history: Vec<Message> = Vec::new();
 
         println!(
             "🔧 Claude REPL with Tools (type natural language, like 'Check ETH balance of Alice')"
@@ -111,18 +122,18 @@ impl RigAgent {
                     println!("{PROCESSING_MESSAGE}");
 
                     // Process through RAG middleware
-                    let query = self.query_rag(&line).await?;
+                    let (query, sources) = self.query_rag(&line).await?;
 
                     // Process input through agent
                     match self
                         .agent
                         .prompt(query)
                         .multi_turn(20)
-                        .with_history(&mut history)
+                        .with_history(&mut self.history)
                         .await
                     {
                         Ok(reply) => {
-                            Self::display_response(&reply);
+                            Self::display_response(&reply, &sources);
                         }
                         Err(e) => {
                             Self::display_prompt_err(e);
@@ -162,9 +173,20 @@ impl RigAgent {
         Ok((tools_list_res, mcp_client))
     }
 
-    /// Displays the agent’s reply in a user-friendly format.
-    pub fn display_response(reply: &str) {
+    /// Displays the agent's reply, followed by a deduplicated "Sources:"
+    /// footer listing the `path:start-end` citations that fed the answer.
+    pub fn display_response(reply: &str, sources: &[String]) {
         println!("\nClaude:\n{reply}\n");
+
+        let mut seen = std::collections::HashSet::new();
+        let unique_sources: Vec<&String> = sources.iter().filter(|s| seen.insert(*s)).collect();
+        if !unique_sources.is_empty() {
+            println!("Sources:");
+            for source in unique_sources {
+                println!("  - {source}");
+            }
+            println!();
+        }
     }
 
     /// Displays the agent’s reply in a user-friendly format.